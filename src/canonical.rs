@@ -0,0 +1,162 @@
+//! Canonical, signature-stable encoding of license payloads.
+//!
+//! [`crate::persistence::LicenseData::to_key`] serializes with `rmp_serde::to_vec_named`,
+//! which is convenient for persistence but not safe to sign over: map-key ordering and future
+//! field additions can change the byte output for data that is otherwise unchanged. The
+//! encoding in this module is deliberately independent of that persistence format: the core
+//! fields are written in a fixed order, with fixed integer widths, and optional fields are
+//! appended in a separate tagged extension section that is only written to when present. That
+//! way adding a new optional field to [`LicensePayloadData`] never changes the bytes of a
+//! payload that doesn't use it, so previously issued signatures stay valid.
+use crate::error::LicenseError;
+use crate::persistence::{LicensePayloadData, LicensedProductData};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as BASE_64_ENGINE;
+use base64::Engine;
+use rmp::encode as rmp_encode;
+
+/// Tag identifying an optional field in the canonical encoding's extension section.
+///
+/// Once assigned to a field, a tag must never be reused or reordered.
+#[repr(u8)]
+enum ExtensionTag {
+    ValidFrom = 1,
+    ValidUntil = 2,
+    DelegatedPublicKey = 3,
+}
+
+/// Value of an optional field in the extension section.
+enum ExtensionValue {
+    UInt(u64),
+    Bytes(Vec<u8>),
+}
+
+/// Encodes a license payload into its canonical byte representation.
+///
+/// Signing and verification must always go through this function, never through
+/// [`crate::persistence::LicenseData::to_key`].
+pub fn canonical_payload_bytes(payload: &LicensePayloadData) -> Result<Vec<u8>, LicenseError> {
+    let mut buf = Vec::new();
+    rmp_encode::write_array_len(&mut buf, 5).unwrap();
+    rmp_encode::write_str(&mut buf, &payload.name).unwrap();
+    rmp_encode::write_str(&mut buf, &payload.email).unwrap();
+    rmp_encode::write_u64(&mut buf, payload.kind as u64).unwrap();
+    rmp_encode::write_u64(&mut buf, payload.created_on).unwrap();
+    write_products(&mut buf, &payload.products);
+    write_extensions(&mut buf, payload)?;
+    Ok(buf)
+}
+
+fn write_products(buf: &mut Vec<u8>, products: &[LicensedProductData]) {
+    rmp_encode::write_array_len(buf, products.len() as u32).unwrap();
+    for product in products {
+        rmp_encode::write_array_len(buf, 3).unwrap();
+        rmp_encode::write_str(buf, &product.id).unwrap();
+        rmp_encode::write_u32(buf, product.min_version).unwrap();
+        rmp_encode::write_u32(buf, product.max_version).unwrap();
+    }
+}
+
+fn write_extensions(buf: &mut Vec<u8>, payload: &LicensePayloadData) -> Result<(), LicenseError> {
+    let mut extensions: Vec<(ExtensionTag, ExtensionValue)> = Vec::new();
+    if let Some(valid_from) = payload.valid_from {
+        extensions.push((ExtensionTag::ValidFrom, ExtensionValue::UInt(valid_from)));
+    }
+    if let Some(valid_until) = payload.valid_until {
+        extensions.push((ExtensionTag::ValidUntil, ExtensionValue::UInt(valid_until)));
+    }
+    if let Some(delegated_public_key) = &payload.delegated_public_key {
+        // These bytes uniquely represent the payload for signing purposes, so a malformed
+        // key must not silently collapse to some other (e.g. absent) encoding.
+        let bytes = BASE_64_ENGINE.decode(delegated_public_key)?;
+        extensions.push((ExtensionTag::DelegatedPublicKey, ExtensionValue::Bytes(bytes)));
+    }
+    // A payload that uses none of the optional fields must encode to exactly the same bytes
+    // as one that predates them, so previously issued signatures keep verifying. Writing the
+    // (even empty) map header unconditionally would append a trailing byte to every payload.
+    if extensions.is_empty() {
+        return Ok(());
+    }
+    rmp_encode::write_map_len(buf, extensions.len() as u32).unwrap();
+    for (tag, value) in extensions {
+        rmp_encode::write_u8(buf, tag as u8).unwrap();
+        match value {
+            ExtensionValue::UInt(value) => {
+                rmp_encode::write_u64(buf, value).unwrap();
+            }
+            ExtensionValue::Bytes(bytes) => {
+                rmp_encode::write_bin(buf, &bytes).unwrap();
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::LicenseKind;
+
+    fn payload_without_optional_fields() -> LicensePayloadData {
+        LicensePayloadData {
+            name: "Joe".to_string(),
+            email: "joe@example.org".to_string(),
+            kind: LicenseKind::Personal,
+            created_on: 0,
+            valid_from: None,
+            valid_until: None,
+            delegated_public_key: None,
+            products: vec![LicensedProductData {
+                id: "foo".to_string(),
+                min_version: 1,
+                max_version: 1,
+            }],
+        }
+    }
+
+    #[test]
+    fn absent_optional_fields_add_no_trailing_bytes() {
+        let payload = payload_without_optional_fields();
+        let mut expected = Vec::new();
+        rmp_encode::write_array_len(&mut expected, 5).unwrap();
+        rmp_encode::write_str(&mut expected, &payload.name).unwrap();
+        rmp_encode::write_str(&mut expected, &payload.email).unwrap();
+        rmp_encode::write_u64(&mut expected, payload.kind as u64).unwrap();
+        rmp_encode::write_u64(&mut expected, payload.created_on).unwrap();
+        rmp_encode::write_array_len(&mut expected, payload.products.len() as u32).unwrap();
+        for product in &payload.products {
+            rmp_encode::write_array_len(&mut expected, 3).unwrap();
+            rmp_encode::write_str(&mut expected, &product.id).unwrap();
+            rmp_encode::write_u32(&mut expected, product.min_version).unwrap();
+            rmp_encode::write_u32(&mut expected, product.max_version).unwrap();
+        }
+        assert_eq!(canonical_payload_bytes(&payload).unwrap(), expected);
+    }
+
+    #[test]
+    fn present_optional_field_changes_the_bytes() {
+        let without_bounds = payload_without_optional_fields();
+        let mut with_valid_from = payload_without_optional_fields();
+        with_valid_from.valid_from = Some(1_000);
+        assert_ne!(
+            canonical_payload_bytes(&without_bounds).unwrap(),
+            canonical_payload_bytes(&with_valid_from).unwrap()
+        );
+    }
+
+    #[test]
+    fn malformed_delegated_public_key_errors_instead_of_collapsing() {
+        let mut payload = payload_without_optional_fields();
+        payload.delegated_public_key = Some("not valid base64!!".to_string());
+        assert!(canonical_payload_bytes(&payload).is_err());
+    }
+
+    #[test]
+    fn encoding_is_deterministic_across_equal_payloads() {
+        let a = payload_without_optional_fields();
+        let b = payload_without_optional_fields();
+        assert_eq!(
+            canonical_payload_bytes(&a).unwrap(),
+            canonical_payload_bytes(&b).unwrap()
+        );
+    }
+}
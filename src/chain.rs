@@ -0,0 +1,194 @@
+//! License delegation via chains of intermediate licenses.
+//!
+//! Importing the intermediate-license idea from the tsproto protocol: a chain starts with a
+//! license signed by a trusted root key, and each subsequent license is signed by the key
+//! that the previous license delegated to, with its validity window constrained to lie within
+//! its parent's. This lets a root key holder grant a reseller scoped, time-bounded signing
+//! authority without ever handing out the root private key.
+use crate::error::LicenseError;
+use crate::persistence::LicenseData;
+use crate::runtime::License;
+use ed25519_dalek::VerifyingKey;
+use thiserror::Error;
+
+/// Error returned when a [`LicenseChain`] fails to verify.
+#[derive(Error, Debug)]
+pub enum ChainError {
+    #[error("license chain is empty")]
+    Empty,
+    #[error("license at chain position {0} is invalid: {1}")]
+    InvalidLicense(usize, LicenseError),
+    #[error("license at chain position {0} isn't authorized to delegate to the next link")]
+    NotDelegated(usize),
+    #[error("license at chain position {0} exceeds its parent's validity bounds")]
+    Bounds(usize),
+}
+
+/// A chain of licenses where each license delegates signing authority to the next, down to a
+/// leaf license that is the one actually used by a product at runtime.
+pub struct LicenseChain(Vec<LicenseData>);
+
+impl LicenseChain {
+    pub fn new(links: Vec<LicenseData>) -> Self {
+        Self(links)
+    }
+
+    pub fn links(&self) -> &[LicenseData] {
+        &self.0
+    }
+
+    /// Verifies this chain against a trusted root public key, returning the verified leaf
+    /// license on success.
+    ///
+    /// Walks from the root down to the leaf, checking each link's signature against the
+    /// public key delegated to it by its parent (or, for the first link, `root_public_key`),
+    /// and that each link's validity window lies within its parent's.
+    pub fn verify(&self, root_public_key: &VerifyingKey) -> Result<License, ChainError> {
+        let mut links = self.0.iter().enumerate();
+        let (_, first_data) = links.next().ok_or(ChainError::Empty)?;
+        let mut current = verify_link(0, first_data, root_public_key)?;
+        for (index, data) in links {
+            let delegated_public_key = current
+                .payload()
+                .delegated_public_key()
+                .ok_or(ChainError::NotDelegated(index - 1))?;
+            let child_public_key = VerifyingKey::from_bytes(delegated_public_key)
+                .map_err(|_| ChainError::InvalidLicense(index, LicenseError::SignatureInvalid))?;
+            let child = verify_link(index, data, &child_public_key)?;
+            if !bounds_contained(&current, &child) {
+                return Err(ChainError::Bounds(index));
+            }
+            current = child;
+        }
+        Ok(current)
+    }
+}
+
+fn verify_link(
+    index: usize,
+    data: &LicenseData,
+    public_key: &VerifyingKey,
+) -> Result<License, ChainError> {
+    let license =
+        License::try_from(data.clone()).map_err(|e| ChainError::InvalidLicense(index, e))?;
+    license
+        .verify(public_key)
+        .map_err(|e| ChainError::InvalidLicense(index, e))?;
+    Ok(license)
+}
+
+fn bounds_contained(parent: &License, child: &License) -> bool {
+    let parent_from = parent.payload().valid_from().unwrap_or(u64::MIN);
+    let parent_until = parent.payload().valid_until().unwrap_or(u64::MAX);
+    let child_from = child.payload().valid_from().unwrap_or(u64::MIN);
+    let child_until = child.payload().valid_until().unwrap_or(u64::MAX);
+    child_from >= parent_from && child_until <= parent_until
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{LicenseKind, LicensedProduct, LicensePayload};
+    use crate::signing::sign;
+    use ed25519_dalek::SigningKey;
+
+    fn products() -> Vec<LicensedProduct> {
+        vec![LicensedProduct {
+            id: "foo".to_string(),
+            min_version: 1,
+            max_version: 1,
+        }]
+    }
+
+    fn payload() -> LicensePayload {
+        LicensePayload {
+            name: "Joe".to_string(),
+            email: "joe@example.org".to_string(),
+            kind: LicenseKind::Personal,
+            created_on: 0,
+            valid_from: None,
+            valid_until: None,
+            delegated_public_key: None,
+            products: products(),
+        }
+    }
+
+    fn signed_license_data(payload: LicensePayload, signing_key: &SigningKey) -> LicenseData {
+        let signature = sign(&payload, signing_key);
+        LicenseData::from(License::new(payload, signature))
+    }
+
+    #[test]
+    fn empty_chain_is_rejected() {
+        let root_key = SigningKey::from_bytes(&[1u8; 32]);
+        let chain = LicenseChain::new(vec![]);
+        assert!(matches!(
+            chain.verify(&root_key.verifying_key()),
+            Err(ChainError::Empty)
+        ));
+    }
+
+    #[test]
+    fn single_link_chain_verifies() {
+        let root_key = SigningKey::from_bytes(&[1u8; 32]);
+        let leaf_data = signed_license_data(payload(), &root_key);
+        let chain = LicenseChain::new(vec![leaf_data]);
+        let leaf = chain
+            .verify(&root_key.verifying_key())
+            .expect("chain should verify");
+        assert_eq!(leaf.payload().name(), "Joe");
+    }
+
+    #[test]
+    fn delegated_chain_verifies() {
+        let root_key = SigningKey::from_bytes(&[1u8; 32]);
+        let intermediate_key = SigningKey::from_bytes(&[2u8; 32]);
+        let mut intermediate_payload = payload();
+        intermediate_payload.delegated_public_key =
+            Some(intermediate_key.verifying_key().to_bytes());
+        let intermediate_data = signed_license_data(intermediate_payload, &root_key);
+        let leaf_data = signed_license_data(payload(), &intermediate_key);
+
+        let chain = LicenseChain::new(vec![intermediate_data, leaf_data]);
+        let leaf = chain
+            .verify(&root_key.verifying_key())
+            .expect("chain should verify");
+        assert_eq!(leaf.payload().name(), "Joe");
+    }
+
+    #[test]
+    fn link_that_is_not_delegated_to_cannot_have_a_successor() {
+        let root_key = SigningKey::from_bytes(&[1u8; 32]);
+        let intermediate_key = SigningKey::from_bytes(&[2u8; 32]);
+        // The root license doesn't delegate to anyone, so it must be a leaf.
+        let root_data = signed_license_data(payload(), &root_key);
+        let leaf_data = signed_license_data(payload(), &intermediate_key);
+
+        let chain = LicenseChain::new(vec![root_data, leaf_data]);
+        assert!(matches!(
+            chain.verify(&root_key.verifying_key()),
+            Err(ChainError::NotDelegated(0))
+        ));
+    }
+
+    #[test]
+    fn child_exceeding_parent_validity_bounds_is_rejected() {
+        let root_key = SigningKey::from_bytes(&[1u8; 32]);
+        let intermediate_key = SigningKey::from_bytes(&[2u8; 32]);
+        let mut intermediate_payload = payload();
+        intermediate_payload.delegated_public_key =
+            Some(intermediate_key.verifying_key().to_bytes());
+        intermediate_payload.valid_until = Some(100);
+        let intermediate_data = signed_license_data(intermediate_payload, &root_key);
+
+        let mut leaf_payload = payload();
+        leaf_payload.valid_until = Some(200);
+        let leaf_data = signed_license_data(leaf_payload, &intermediate_key);
+
+        let chain = LicenseChain::new(vec![intermediate_data, leaf_data]);
+        assert!(matches!(
+            chain.verify(&root_key.verifying_key()),
+            Err(ChainError::Bounds(1))
+        ));
+    }
+}
@@ -0,0 +1,100 @@
+//! Structured error type for license decoding, validation and verification failures.
+//!
+//! Until now this crate collapsed everything into an opaque `anyhow::Error`, which is fine
+//! for logging but useless for a UI that wants to tell a user *why* their license key was
+//! rejected. [`LicenseError`] gives callers something to match on instead.
+use thiserror::Error;
+
+/// Why a license key could not be decoded, validated, or verified.
+#[derive(Error, Debug)]
+pub enum LicenseError {
+    #[error("license key is not valid base64: {0}")]
+    Base64Decode(#[from] base64::DecodeError),
+    #[error("license key is not valid MessagePack: {0}")]
+    MsgPackDecode(#[from] rmp_serde::decode::Error),
+    #[error("license data failed validation")]
+    Validation(Vec<FieldError>),
+    #[error("license signature is invalid")]
+    SignatureInvalid,
+    #[error("license is not valid yet")]
+    NotYetValid,
+    #[error("license has expired")]
+    Expired,
+}
+
+/// A single field-level validation failure, identified by its dotted path (e.g.
+/// `"products[0].id"`).
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct FieldError {
+    pub field: String,
+    pub code: String,
+}
+
+impl From<validator::ValidationErrors> for LicenseError {
+    fn from(errors: validator::ValidationErrors) -> Self {
+        LicenseError::Validation(flatten_validation_errors(&errors, ""))
+    }
+}
+
+fn flatten_validation_errors(errors: &validator::ValidationErrors, prefix: &str) -> Vec<FieldError> {
+    let mut result = Vec::new();
+    for (field, kind) in errors.errors() {
+        let path = if prefix.is_empty() {
+            field.to_string()
+        } else {
+            format!("{prefix}.{field}")
+        };
+        match kind {
+            validator::ValidationErrorsKind::Field(field_errors) => {
+                for field_error in field_errors {
+                    result.push(FieldError {
+                        field: path.clone(),
+                        code: field_error.code.to_string(),
+                    });
+                }
+            }
+            validator::ValidationErrorsKind::Struct(nested) => {
+                result.extend(flatten_validation_errors(nested, &path));
+            }
+            validator::ValidationErrorsKind::List(nested_by_index) => {
+                for (index, nested) in nested_by_index {
+                    result.extend(flatten_validation_errors(nested, &format!("{path}[{index}]")));
+                }
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::{LicensePayloadData, LicensedProductData};
+    use crate::runtime::LicenseKind;
+    use validator::Validate;
+
+    #[test]
+    fn invalid_payload_flattens_into_dotted_field_errors() {
+        let payload = LicensePayloadData {
+            name: "".to_string(),
+            email: "not-an-email".to_string(),
+            kind: LicenseKind::Personal,
+            created_on: 0,
+            valid_from: None,
+            valid_until: None,
+            delegated_public_key: None,
+            products: vec![LicensedProductData {
+                id: "".to_string(),
+                min_version: 1,
+                max_version: 1,
+            }],
+        };
+        let error: LicenseError = payload.validate().unwrap_err().into();
+        let LicenseError::Validation(fields) = error else {
+            panic!("expected a Validation error");
+        };
+        assert!(fields.iter().any(|f| f.field == "name"));
+        assert!(fields.iter().any(|f| f.field == "email"));
+        assert!(fields.iter().any(|f| f.field == "products[0].id"));
+    }
+}
@@ -0,0 +1,14 @@
+//! Data model and verification logic for helgoboss license keys.
+//!
+//! A license key is a base64-encoded, MessagePack-serialized [`persistence::LicenseData`]
+//! envelope around a [`runtime::LicensePayload`]. The [`persistence`] module deals with the
+//! on-disk/on-the-wire representation (which must stay backward-compatible), while
+//! [`runtime`] holds the validated, ready-to-use types that the rest of an application
+//! interacts with.
+
+pub mod canonical;
+pub mod chain;
+pub mod error;
+pub mod persistence;
+pub mod runtime;
+pub mod signing;
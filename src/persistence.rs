@@ -1,12 +1,10 @@
+use crate::error::LicenseError;
 use crate::runtime::{License, LicenseKind, LicensePayload, LicensedProduct};
 use base64::engine::general_purpose::URL_SAFE_NO_PAD as BASE_64_ENGINE;
 use base64::Engine;
 use serde::{Deserialize, Serialize};
 use validator::{Validate, ValidationError};
 
-/// At the moment, we don't care about distinguishing between different errors.
-type GenericError = anyhow::Error;
-
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
 pub struct LicenseKey(String);
 
@@ -31,6 +29,7 @@ pub struct LicenseData {
 ///
 /// Serialization and deserialization must be backward-compatible because we persist this on disk!
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Validate, Serialize, Deserialize)]
+#[validate(schema(function = "validate_payload_bounds"))]
 pub struct LicensePayloadData {
     /// License owner name.
     #[validate(length(min = 1))]
@@ -42,6 +41,20 @@ pub struct LicensePayloadData {
     pub kind: LicenseKind,
     /// Unix timestamp (seconds since 1970-01-01 00:00:00).
     pub created_on: u64,
+    /// Unix timestamp from which this license is valid. Absent means "no lower bound".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_from: Option<u64>,
+    /// Unix timestamp until which this license is valid. Absent means "no upper bound".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<u64>,
+    /// Base64-encoded Ed25519 public key that this license delegates signing authority to.
+    ///
+    /// If present, this license acts as an intermediate link in a
+    /// [`crate::chain::LicenseChain`] and the holder of the matching private key may sign
+    /// child licenses whose validity window must lie within this license's own. Absent means
+    /// this license cannot delegate further.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delegated_public_key: Option<String>,
     /// Products included in this license.
     #[validate(length(min = 1))]
     #[validate]
@@ -70,7 +83,7 @@ impl LicenseKey {
 }
 
 impl LicenseData {
-    pub fn try_from_key(key: &LicenseKey) -> anyhow::Result<Self> {
+    pub fn try_from_key(key: &LicenseKey) -> Result<Self, LicenseError> {
         let bytes = BASE_64_ENGINE.decode(&key.0)?;
         let data = rmp_serde::from_slice(&bytes)?;
         Ok(data)
@@ -93,7 +106,7 @@ impl From<License> for LicenseData {
 }
 
 impl TryFrom<LicenseData> for License {
-    type Error = GenericError;
+    type Error = LicenseError;
 
     fn try_from(data: LicenseData) -> Result<Self, Self::Error> {
         data.validate()?;
@@ -110,21 +123,37 @@ impl From<LicensePayload> for LicensePayloadData {
             email: value.email,
             kind: value.kind,
             created_on: value.created_on,
+            valid_from: value.valid_from,
+            valid_until: value.valid_until,
+            delegated_public_key: value
+                .delegated_public_key
+                .map(|key| BASE_64_ENGINE.encode(key)),
             products: value.products.into_iter().map(|p| p.into()).collect(),
         }
     }
 }
 
 impl TryFrom<LicensePayloadData> for LicensePayload {
-    type Error = GenericError;
+    type Error = LicenseError;
 
     fn try_from(data: LicensePayloadData) -> Result<Self, Self::Error> {
         data.validate()?;
+        // `validate()` already checked that, if present, this decodes to exactly 32 bytes.
+        let delegated_public_key = data.delegated_public_key.map(|key| {
+            BASE_64_ENGINE
+                .decode(key)
+                .expect("already validated as base64")
+                .try_into()
+                .expect("already validated as 32 bytes")
+        });
         let payload = Self {
             name: data.name,
             email: data.email,
             kind: data.kind,
             created_on: data.created_on,
+            valid_from: data.valid_from,
+            valid_until: data.valid_until,
+            delegated_public_key,
             products: data
                 .products
                 .into_iter()
@@ -156,6 +185,23 @@ fn validate_product(product: &LicensedProductData) -> Result<(), ValidationError
     Ok(())
 }
 
+fn validate_payload_bounds(payload: &LicensePayloadData) -> Result<(), ValidationError> {
+    if let (Some(valid_from), Some(valid_until)) = (payload.valid_from, payload.valid_until) {
+        if valid_until < valid_from {
+            return Err(ValidationError::new("invalid_validity_bounds"));
+        }
+    }
+    if let Some(delegated_public_key) = &payload.delegated_public_key {
+        let decoded = BASE_64_ENGINE
+            .decode(delegated_public_key)
+            .map_err(|_| ValidationError::new("invalid_delegated_public_key"))?;
+        if decoded.len() != 32 {
+            return Err(ValidationError::new("invalid_delegated_public_key"));
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -169,6 +215,9 @@ mod tests {
                 email: "joe@example.org".to_string(),
                 kind: LicenseKind::Personal,
                 created_on: 0,
+                valid_from: None,
+                valid_until: None,
+                delegated_public_key: None,
                 products: vec![LicensedProductData {
                     id: "foo".to_string(),
                     min_version: 1,
@@ -196,6 +245,9 @@ mod tests {
                 email: "joe@example.org".to_string(),
                 kind: LicenseKind::Personal,
                 created_on: 0,
+                valid_from: None,
+                valid_until: None,
+                delegated_public_key: None,
                 products: vec![LicensedProductData {
                     id: "foo".to_string(),
                     min_version: 1,
@@ -217,6 +269,9 @@ mod tests {
                 email: "joe@example.org".to_string(),
                 kind: LicenseKind::Personal,
                 created_on: 0,
+                valid_from: None,
+                valid_until: None,
+                delegated_public_key: None,
                 products: vec![LicensedProductData {
                     id: "foo".to_string(),
                     min_version: 1,
@@ -246,6 +301,9 @@ mod tests {
                 email: "joe".to_string(),
                 kind: LicenseKind::Personal,
                 created_on: 0,
+                valid_from: None,
+                valid_until: None,
+                delegated_public_key: None,
                 products: vec![],
             },
             signature: "".to_string(),
@@ -265,6 +323,9 @@ mod tests {
                 email: "joe@example.org".to_string(),
                 kind: LicenseKind::Personal,
                 created_on: 0,
+                valid_from: None,
+                valid_until: None,
+                delegated_public_key: None,
                 products: vec![LicensedProductData {
                     id: "foo".to_string(),
                     min_version: 1,
@@ -279,4 +340,57 @@ mod tests {
         // Then
         assert_eq!(original_license_data, serialized_license_data);
     }
+
+    #[test]
+    fn valid_until_before_valid_from_is_rejected() {
+        // Given
+        let license_data = LicenseData {
+            payload: LicensePayloadData {
+                name: "Joe".to_string(),
+                email: "joe@example.org".to_string(),
+                kind: LicenseKind::Personal,
+                created_on: 0,
+                valid_from: Some(100),
+                valid_until: Some(99),
+                delegated_public_key: None,
+                products: vec![LicensedProductData {
+                    id: "foo".to_string(),
+                    min_version: 1,
+                    max_version: 1,
+                }],
+            },
+            signature: "aGVsbG8".to_string(),
+        };
+        // When
+        let license = License::try_from(license_data);
+        // Then
+        license.expect_err("valid_until before valid_from should be rejected");
+    }
+
+    #[test]
+    fn validity_window_round_trips_through_persistence() {
+        // Given
+        let license_data = LicenseData {
+            payload: LicensePayloadData {
+                name: "Joe".to_string(),
+                email: "joe@example.org".to_string(),
+                kind: LicenseKind::Personal,
+                created_on: 0,
+                valid_from: Some(100),
+                valid_until: Some(200),
+                delegated_public_key: None,
+                products: vec![LicensedProductData {
+                    id: "foo".to_string(),
+                    min_version: 1,
+                    max_version: 1,
+                }],
+            },
+            signature: "aGVsbG8".to_string(),
+        };
+        // When
+        let license = License::try_from(license_data).unwrap();
+        // Then
+        assert_eq!(license.payload().valid_from(), Some(100));
+        assert_eq!(license.payload().valid_until(), Some(200));
+    }
 }
@@ -0,0 +1,233 @@
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+/// Kind of license.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum LicenseKind {
+    Personal,
+    Business,
+}
+
+/// A complete license: a payload plus the signature over that payload.
+///
+/// Converting a [`crate::persistence::LicenseData`] into this type with `TryFrom` validates the
+/// data first. [`License::new`] does not validate its arguments. Neither way of constructing a
+/// license checks its signature; that is what [`License::verify`] does.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct License {
+    payload: LicensePayload,
+    signature: Vec<u8>,
+}
+
+impl License {
+    pub fn new(payload: LicensePayload, signature: Vec<u8>) -> Self {
+        Self { payload, signature }
+    }
+
+    pub fn payload(&self) -> &LicensePayload {
+        &self.payload
+    }
+
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// Determines whether this license is valid at the given point in time (Unix timestamp).
+    pub fn validity_status(&self, now: u64) -> ValidityStatus {
+        self.payload.validity_status(now)
+    }
+
+    /// The range of versions of `product_id` that this license covers, if any.
+    pub fn entitlement_for(&self, product_id: &str) -> Option<RangeInclusive<u32>> {
+        self.payload.entitlement_for(product_id)
+    }
+
+    /// Whether this license covers the given product ID and version.
+    pub fn covers(&self, product_id: &str, version: u32) -> bool {
+        self.payload.covers(product_id, version)
+    }
+
+    /// Whether `product_id` at `version` is licensed right now (Unix timestamp `now`), i.e.
+    /// the license both covers that product/version and is currently within its validity
+    /// window.
+    pub fn is_licensed_for(&self, product_id: &str, version: u32, now: u64) -> bool {
+        self.validity_status(now) == ValidityStatus::Valid && self.covers(product_id, version)
+    }
+}
+
+/// A validated license payload: who the license is for, what it covers, and when it was
+/// created.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct LicensePayload {
+    pub name: String,
+    pub email: String,
+    pub kind: LicenseKind,
+    pub created_on: u64,
+    pub valid_from: Option<u64>,
+    pub valid_until: Option<u64>,
+    pub delegated_public_key: Option<[u8; 32]>,
+    pub products: Vec<LicensedProduct>,
+}
+
+impl LicensePayload {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn email(&self) -> &str {
+        &self.email
+    }
+
+    pub fn kind(&self) -> LicenseKind {
+        self.kind
+    }
+
+    pub fn created_on(&self) -> u64 {
+        self.created_on
+    }
+
+    pub fn valid_from(&self) -> Option<u64> {
+        self.valid_from
+    }
+
+    pub fn valid_until(&self) -> Option<u64> {
+        self.valid_until
+    }
+
+    pub fn delegated_public_key(&self) -> Option<&[u8; 32]> {
+        self.delegated_public_key.as_ref()
+    }
+
+    pub fn products(&self) -> &[LicensedProduct] {
+        &self.products
+    }
+
+    /// The range of versions of `product_id` that this payload covers, if any.
+    pub fn entitlement_for(&self, product_id: &str) -> Option<RangeInclusive<u32>> {
+        self.products
+            .iter()
+            .find(|product| product.id == product_id)
+            .map(LicensedProduct::version_range)
+    }
+
+    /// Whether this payload covers the given product ID and version.
+    pub fn covers(&self, product_id: &str, version: u32) -> bool {
+        self.entitlement_for(product_id)
+            .is_some_and(|range| range.contains(&version))
+    }
+
+    /// Determines whether this payload is valid at the given point in time (Unix timestamp).
+    pub fn validity_status(&self, now: u64) -> ValidityStatus {
+        if let Some(valid_from) = self.valid_from {
+            if now < valid_from {
+                return ValidityStatus::NotYetValid;
+            }
+        }
+        if let Some(valid_until) = self.valid_until {
+            if now > valid_until {
+                return ValidityStatus::Expired;
+            }
+        }
+        ValidityStatus::Valid
+    }
+}
+
+/// Whether a license is valid at a given point in time.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ValidityStatus {
+    /// The license's validity window hasn't started yet.
+    NotYetValid,
+    /// The license is currently valid.
+    Valid,
+    /// The license's validity window is over.
+    Expired,
+}
+
+/// A single product covered by a license, and the range of versions it covers.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct LicensedProduct {
+    pub id: String,
+    pub min_version: u32,
+    pub max_version: u32,
+}
+
+impl LicensedProduct {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn version_range(&self) -> RangeInclusive<u32> {
+        self.min_version..=self.max_version
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload() -> LicensePayload {
+        LicensePayload {
+            name: "Joe".to_string(),
+            email: "joe@example.org".to_string(),
+            kind: LicenseKind::Personal,
+            created_on: 0,
+            valid_from: None,
+            valid_until: None,
+            delegated_public_key: None,
+            products: vec![LicensedProduct {
+                id: "foo".to_string(),
+                min_version: 1,
+                max_version: 3,
+            }],
+        }
+    }
+
+    #[test]
+    fn validity_status_without_bounds_is_always_valid() {
+        assert_eq!(payload().validity_status(0), ValidityStatus::Valid);
+        assert_eq!(payload().validity_status(u64::MAX), ValidityStatus::Valid);
+    }
+
+    #[test]
+    fn validity_status_respects_valid_from() {
+        let mut payload = payload();
+        payload.valid_from = Some(100);
+        assert_eq!(payload.validity_status(99), ValidityStatus::NotYetValid);
+        assert_eq!(payload.validity_status(100), ValidityStatus::Valid);
+    }
+
+    #[test]
+    fn validity_status_respects_valid_until() {
+        let mut payload = payload();
+        payload.valid_until = Some(100);
+        assert_eq!(payload.validity_status(100), ValidityStatus::Valid);
+        assert_eq!(payload.validity_status(101), ValidityStatus::Expired);
+    }
+
+    #[test]
+    fn entitlement_for_returns_the_covered_version_range() {
+        assert_eq!(payload().entitlement_for("foo"), Some(1..=3));
+        assert_eq!(payload().entitlement_for("bar"), None);
+    }
+
+    #[test]
+    fn covers_checks_product_id_and_version_range() {
+        let payload = payload();
+        assert!(payload.covers("foo", 1));
+        assert!(payload.covers("foo", 3));
+        assert!(!payload.covers("foo", 4));
+        assert!(!payload.covers("bar", 1));
+    }
+
+    #[test]
+    fn is_licensed_for_requires_both_validity_and_coverage() {
+        let mut bounded = payload();
+        bounded.valid_from = Some(100);
+        bounded.valid_until = Some(200);
+        let license = License::new(bounded, vec![]);
+
+        assert!(license.is_licensed_for("foo", 1, 150));
+        assert!(!license.is_licensed_for("foo", 1, 50));
+        assert!(!license.is_licensed_for("bar", 1, 150));
+    }
+}
@@ -0,0 +1,148 @@
+//! Ed25519 signing and verification of license payloads.
+//!
+//! Issuing a license means serializing its payload and signing those bytes with the private
+//! key that only the issuer holds. Verifying a license means redoing that serialization and
+//! checking the embedded signature against the issuer's public key. Both sides must agree on
+//! the exact bytes that get signed, which is why [`sign`] and [`License::verify`] both go
+//! through the canonical encoding in [`crate::canonical`] rather than the persistence format.
+use crate::canonical::canonical_payload_bytes;
+use crate::error::LicenseError;
+use crate::persistence::LicensePayloadData;
+use crate::runtime::{License, LicensePayload, ValidityStatus};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+
+/// Signs the given license payload with the issuer's private key.
+///
+/// The returned bytes are meant to end up in [`crate::persistence::LicenseData::signature`].
+///
+/// # Panics
+///
+/// Panics if `payload` cannot be canonically encoded. A [`LicensePayload`] holds its
+/// `delegated_public_key` as raw bytes, which are base64-encoded and decoded again on the way
+/// to the canonical form, so this encoding does not fail for any `LicensePayload`.
+pub fn sign(payload: &LicensePayload, private_key: &SigningKey) -> Vec<u8> {
+    let bytes = signable_bytes(payload).expect("license payload should encode canonically");
+    let signature: Signature = private_key.sign(&bytes);
+    signature.to_bytes().to_vec()
+}
+
+impl License {
+    /// Checks this license's signature against the issuer's public key.
+    pub fn verify(&self, public_key: &VerifyingKey) -> Result<(), LicenseError> {
+        let signature = Signature::from_slice(self.signature())
+            .map_err(|_| LicenseError::SignatureInvalid)?;
+        let bytes = signable_bytes(self.payload())?;
+        public_key
+            .verify(&bytes, &signature)
+            .map_err(|_| LicenseError::SignatureInvalid)
+    }
+
+    /// Checks this license's signature, and that it is currently within its validity window
+    /// (Unix timestamp `now`).
+    ///
+    /// Unlike [`Self::verify`], this distinguishes a bad signature from a license that simply
+    /// isn't valid yet or has expired, so callers can tell the two apart.
+    pub fn verify_at(&self, public_key: &VerifyingKey, now: u64) -> Result<(), LicenseError> {
+        self.verify(public_key)?;
+        match self.validity_status(now) {
+            ValidityStatus::NotYetValid => Err(LicenseError::NotYetValid),
+            ValidityStatus::Expired => Err(LicenseError::Expired),
+            ValidityStatus::Valid => Ok(()),
+        }
+    }
+}
+
+fn signable_bytes(payload: &LicensePayload) -> Result<Vec<u8>, LicenseError> {
+    let data = LicensePayloadData::from(payload.clone());
+    canonical_payload_bytes(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::LicenseKind;
+
+    fn payload() -> LicensePayload {
+        LicensePayload {
+            name: "Joe".to_string(),
+            email: "joe@example.org".to_string(),
+            kind: LicenseKind::Personal,
+            created_on: 0,
+            valid_from: None,
+            valid_until: None,
+            delegated_public_key: None,
+            products: vec![],
+        }
+    }
+
+    #[test]
+    fn signed_license_verifies_against_the_signing_key() {
+        let private_key = SigningKey::from_bytes(&[1u8; 32]);
+        let payload = payload();
+        let signature = sign(&payload, &private_key);
+        let license = License::new(payload, signature);
+        assert!(license.verify(&private_key.verifying_key()).is_ok());
+    }
+
+    #[test]
+    fn tampered_payload_fails_verification() {
+        let private_key = SigningKey::from_bytes(&[1u8; 32]);
+        let signature = sign(&payload(), &private_key);
+        let mut tampered = payload();
+        tampered.name = "Jane".to_string();
+        let license = License::new(tampered, signature);
+        assert!(matches!(
+            license.verify(&private_key.verifying_key()),
+            Err(LicenseError::SignatureInvalid)
+        ));
+    }
+
+    #[test]
+    fn signature_from_a_different_key_fails_verification() {
+        let private_key = SigningKey::from_bytes(&[1u8; 32]);
+        let other_key = SigningKey::from_bytes(&[2u8; 32]);
+        let signature = sign(&payload(), &private_key);
+        let license = License::new(payload(), signature);
+        assert!(matches!(
+            license.verify(&other_key.verifying_key()),
+            Err(LicenseError::SignatureInvalid)
+        ));
+    }
+
+    #[test]
+    fn verify_at_rejects_a_license_that_is_not_yet_valid() {
+        let private_key = SigningKey::from_bytes(&[1u8; 32]);
+        let mut not_yet_valid = payload();
+        not_yet_valid.valid_from = Some(100);
+        let signature = sign(&not_yet_valid, &private_key);
+        let license = License::new(not_yet_valid, signature);
+        assert!(matches!(
+            license.verify_at(&private_key.verifying_key(), 50),
+            Err(LicenseError::NotYetValid)
+        ));
+    }
+
+    #[test]
+    fn verify_at_rejects_an_expired_license() {
+        let private_key = SigningKey::from_bytes(&[1u8; 32]);
+        let mut expired = payload();
+        expired.valid_until = Some(100);
+        let signature = sign(&expired, &private_key);
+        let license = License::new(expired, signature);
+        assert!(matches!(
+            license.verify_at(&private_key.verifying_key(), 101),
+            Err(LicenseError::Expired)
+        ));
+    }
+
+    #[test]
+    fn verify_at_accepts_a_license_within_its_validity_window() {
+        let private_key = SigningKey::from_bytes(&[1u8; 32]);
+        let mut bounded = payload();
+        bounded.valid_from = Some(100);
+        bounded.valid_until = Some(200);
+        let signature = sign(&bounded, &private_key);
+        let license = License::new(bounded, signature);
+        assert!(license.verify_at(&private_key.verifying_key(), 150).is_ok());
+    }
+}